@@ -0,0 +1,134 @@
+use axum::{
+    Json,
+    body::Body,
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use chrono::Utc;
+use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::{AppError, AppState};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    pub iat: usize,
+    pub exp: usize,
+}
+
+// DTO for the login request
+#[derive(Debug, Deserialize)]
+pub struct LoginRequest {
+    pub username: String,
+    pub password: String,
+}
+
+// DTO for the login response
+#[derive(Debug, Serialize)]
+pub struct LoginResponse {
+    pub token: String,
+}
+
+/// Issue an HS256 token for the configured admin user.
+///
+/// There is no user store in this demo, so credentials are compared against
+/// `ADMIN_USERNAME`/`ADMIN_PASSWORD`, mirroring the rest of the project's
+/// env-var-driven configuration.
+pub async fn login(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<LoginRequest>,
+) -> crate::Result<Json<LoginResponse>> {
+    let jwt_secret = state
+        .config
+        .jwt_secret
+        .as_ref()
+        .ok_or(AppError::Unauthorized)?;
+
+    let admin_username = std::env::var("ADMIN_USERNAME").ok();
+    let admin_password = std::env::var("ADMIN_PASSWORD").ok();
+
+    if admin_username.is_none() || admin_password.is_none() {
+        tracing::warn!(
+            "JWT_SECRET is set but ADMIN_USERNAME/ADMIN_PASSWORD are not; \
+             falling back to the insecure default admin/admin login"
+        );
+    }
+
+    let admin_username = admin_username.unwrap_or_else(|| "admin".to_string());
+    let admin_password = admin_password.unwrap_or_else(|| "admin".to_string());
+
+    let matches = constant_time_eq(payload.username.as_bytes(), admin_username.as_bytes())
+        & constant_time_eq(payload.password.as_bytes(), admin_password.as_bytes());
+    if !matches {
+        return Err(AppError::Unauthorized);
+    }
+
+    let now = Utc::now();
+    let iat = now.timestamp() as usize;
+    let exp = (now + state.config.jwt_expires_in).timestamp() as usize;
+
+    let claims = Claims {
+        sub: payload.username,
+        iat,
+        exp,
+    };
+
+    let token = encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(jwt_secret.as_bytes()),
+    )
+    .map_err(|_| AppError::Unauthorized)?;
+
+    Ok(Json(LoginResponse { token }))
+}
+
+/// Middleware validating the `Authorization: Bearer` header against the
+/// configured secret. A no-op when `JWT_SECRET` is unset so the demo still
+/// works with no setup.
+pub async fn require_auth(
+    State(state): State<Arc<AppState>>,
+    req: Request<Body>,
+    next: Next,
+) -> crate::Result<Response> {
+    let Some(jwt_secret) = state.config.jwt_secret.as_ref() else {
+        return Ok(next.run(req).await);
+    };
+
+    let token = req
+        .headers()
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|header| header.to_str().ok())
+        .and_then(|header| header.strip_prefix("Bearer "))
+        .ok_or(AppError::Unauthorized)?;
+
+    let claims = decode::<Claims>(
+        token,
+        &DecodingKey::from_secret(jwt_secret.as_bytes()),
+        &Validation::default(),
+    )
+    .map_err(|e| match e.kind() {
+        jsonwebtoken::errors::ErrorKind::ExpiredSignature => AppError::TokenExpired,
+        _ => AppError::Unauthorized,
+    })?
+    .claims;
+
+    let _ = claims;
+    Ok(next.run(req).await)
+}
+
+/// Compare two byte strings in constant time, so a login attempt can't be
+/// used to time out the admin credentials character by character.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut diff: u8 = 0;
+    for (x, y) in a.iter().zip(b.iter()) {
+        diff |= x ^ y;
+    }
+    diff == 0
+}
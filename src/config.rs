@@ -0,0 +1,59 @@
+use std::env;
+
+use chrono::Duration;
+
+/// Runtime configuration loaded from the environment.
+///
+/// JWT auth is optional: when `JWT_SECRET` is unset the demo runs wide open,
+/// matching the project's "no setup required" default.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub jwt_secret: Option<String>,
+    pub jwt_expires_in: Duration,
+}
+
+impl Config {
+    pub fn init() -> Self {
+        let jwt_secret = env::var("JWT_SECRET").ok();
+        let jwt_expires_in = env::var("JWT_EXPIRES_IN")
+            .ok()
+            .and_then(|v| parse_expires_in(&v))
+            // JWT_MAXAGE predates JWT_EXPIRES_IN and is kept as a fallback,
+            // in minutes, for deployments that still set it
+            .or_else(|| {
+                env::var("JWT_MAXAGE")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .map(Duration::minutes)
+            })
+            .unwrap_or_else(|| Duration::minutes(60));
+
+        Self {
+            jwt_secret,
+            jwt_expires_in,
+        }
+    }
+
+    pub fn auth_enabled(&self) -> bool {
+        self.jwt_secret.is_some()
+    }
+}
+
+/// Parse a short duration string such as `"15m"`, `"1h"` or `"2d"` (a bare
+/// number is treated as minutes, matching the `"60m"` default).
+fn parse_expires_in(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (amount, unit) = match value.char_indices().last() {
+        Some((idx, c)) if c.is_ascii_alphabetic() => (&value[..idx], c),
+        _ => (value, 'm'),
+    };
+    let amount: i64 = amount.parse().ok()?;
+
+    match unit {
+        's' => Some(Duration::seconds(amount)),
+        'm' => Some(Duration::minutes(amount)),
+        'h' => Some(Duration::hours(amount)),
+        'd' => Some(Duration::days(amount)),
+        _ => None,
+    }
+}
@@ -2,29 +2,59 @@ use axum::{
     Json, Router,
     extract::{Path, State},
     http::StatusCode,
-    response::{Html, IntoResponse, Redirect, Response},
+    middleware,
+    response::{
+        Html, IntoResponse, Redirect, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
     routing::{get, post},
 };
+use axum_server::{Handle, tls_rustls::RustlsConfig};
 use chrono::{DateTime, Utc};
 use dashmap::DashMap;
+use futures_util::stream::Stream;
 use nanoid::nanoid;
 use serde::{Deserialize, Serialize};
-use std::time::Instant;
+use std::convert::Infallible;
+use std::time::{Duration, Instant};
 use std::{
     env,
     net::SocketAddr,
     sync::Arc,
 };
 use thiserror::Error;
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::{StreamExt as _, wrappers::BroadcastStream};
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+mod auth;
+mod config;
+mod resolve;
+mod store;
+use config::Config;
+use store::{SledStore, Store};
+
 // App state
 #[derive(Clone)]
 struct AppState {
     urls: Arc<DashMap<String, Url>>,
     index_html: String,
+    store: Arc<dyn Store>,
+    analytics_tx: broadcast::Sender<AnalyticsEvent>,
+    config: Config,
+    verify_urls: bool,
+    http_client: reqwest::Client,
+    persist_tx: mpsc::UnboundedSender<Url>,
+}
+
+// Live analytics events broadcast over SSE
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AnalyticsEvent {
+    UrlCreated { short_code: String, original_url: String },
+    Click { short_code: String, access_count: i64 },
 }
 
 // URL model
@@ -35,12 +65,34 @@ struct Url {
     short_code: String,
     created_at: DateTime<Utc>,
     access_count: i64,
+    expires_at: Option<DateTime<Utc>>,
+}
+
+impl Url {
+    fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= Utc::now())
+    }
 }
 
 // DTO for creating a new short URL
 #[derive(Debug, Deserialize)]
 struct CreateUrlRequest {
     url: String,
+    ttl_seconds: Option<i64>,
+    custom_code: Option<String>,
+}
+
+// Route prefixes and other paths a custom code must not shadow
+const RESERVED_CODES: &[&str] = &["", "api"];
+
+// Custom codes are kept to the same character set nanoid already generates
+fn is_valid_custom_code(code: &str) -> bool {
+    !code.is_empty()
+        && code.len() <= 32
+        && code
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        && !RESERVED_CODES.contains(&code)
 }
 
 // DTO for URL response
@@ -51,6 +103,7 @@ struct UrlResponse {
     short_url: String,
     created_at: DateTime<Utc>,
     access_count: i64,
+    expires_at: Option<DateTime<Utc>>,
 }
 
 // DTO for analytics response
@@ -68,6 +121,16 @@ enum AppError {
     NotFound,
     #[error("Invalid URL")]
     InvalidUrl,
+    #[error("Unauthorized")]
+    Unauthorized,
+    #[error("Token expired")]
+    TokenExpired,
+    #[error("Unreachable URL")]
+    Unreachable,
+    #[error("Link expired")]
+    Gone,
+    #[error("Code already taken")]
+    CodeTaken,
 }
 
 impl IntoResponse for AppError {
@@ -75,6 +138,14 @@ impl IntoResponse for AppError {
         let (status, message) = match self {
             AppError::NotFound => (StatusCode::NOT_FOUND, "URL not found".to_string()),
             AppError::InvalidUrl => (StatusCode::BAD_REQUEST, "Invalid URL provided".to_string()),
+            AppError::Unauthorized => (StatusCode::UNAUTHORIZED, "Unauthorized".to_string()),
+            AppError::TokenExpired => (StatusCode::UNAUTHORIZED, "Token expired".to_string()),
+            AppError::Unreachable => (
+                StatusCode::BAD_GATEWAY,
+                "Target URL could not be verified".to_string(),
+            ),
+            AppError::Gone => (StatusCode::GONE, "Link has expired".to_string()),
+            AppError::CodeTaken => (StatusCode::CONFLICT, "Short code already in use".to_string()),
         };
         (status, Json(serde_json::json!({ "error": message }))).into_response()
     }
@@ -94,64 +165,165 @@ async fn main() -> anyhow::Result<()> {
     let index_html = tokio::fs::read_to_string("static/index.html")
         .await
         .unwrap_or_else(|_| "<h1>Failed to load index.html</h1>".to_string());
+
+    // Get persistence path from environment or use default
+    let database_path = env::var("DATABASE_PATH").unwrap_or_else(|_| "data/urls.db".to_string());
+    let store: Arc<dyn Store> = Arc::new(SledStore::open(&database_path)?);
+
+    // Rehydrate the in-memory cache from the persistent store
+    let urls = DashMap::new();
+    for url in store.iter()? {
+        urls.insert(url.short_code.clone(), url);
+    }
+    tracing::info!("Loaded {} URLs from {}", urls.len(), database_path);
+
+    let (analytics_tx, _) = broadcast::channel(1024);
+    let config = Config::init();
+    if config.auth_enabled() {
+        tracing::info!("JWT auth enabled");
+    } else {
+        tracing::info!("JWT_SECRET not set, running without auth");
+    }
+
+    let verify_urls = env::var("VERIFY_URLS")
+        .map(|v| v == "true")
+        .unwrap_or(false);
+
+    // A single writer task processes persisted click counts in the order
+    // they're sent, so concurrent redirects for the same code can't race
+    // each other and write a stale count over a fresher one.
+    let (persist_tx, mut persist_rx) = mpsc::unbounded_channel::<Url>();
+    let persist_store = store.clone();
+    tokio::spawn(async move {
+        while let Some(url) = persist_rx.recv().await {
+            if let Err(e) = persist_store.insert(&url) {
+                tracing::error!("Failed to persist access count for {}: {:?}", url.short_code, e);
+            }
+        }
+    });
+
     let state = Arc::new(AppState {
-        urls: Arc::new(DashMap::new()),
+        urls: Arc::new(urls),
         index_html,
+        store,
+        analytics_tx,
+        config,
+        verify_urls,
+        http_client: resolve::client(),
+        persist_tx,
     });
 
+    // Periodically sweep expired links out of the cache and the store
+    tokio::spawn(sweep_expired_urls(state.clone()));
+
     let cors = CorsLayer::new()
         .allow_origin(Any)
         .allow_methods(Any)
         .allow_headers(Any);
 
-    let app = Router::new()
-        .route("/", get(index_handler))
+    let protected = Router::new()
         .route("/api/shorten", post(create_short_url))
         .route("/api/urls", get(get_all_urls))
         .route("/api/analytics", get(get_analytics))
+        .route("/api/analytics/stream", get(analytics_stream))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::require_auth,
+        ));
+
+    let app = Router::new()
+        .route("/", get(index_handler))
+        .route("/api/login", post(auth::login))
         .route("/{short_code}", get(redirect_to_original))
+        .merge(protected)
         .layer(TraceLayer::new_for_http())
         .layer(cors)
         .with_state(state);
 
     let addr = SocketAddr::from(([0, 0, 0, 0], 3000));
-    tracing::info!("Listening on {}", addr);
-    let listener = tokio::net::TcpListener::bind(&addr).await?;
-
-    // Graceful shutdown: listen for SIGINT or SIGTERM
-    let shutdown_signal = async {
-        use tokio::signal;
-        
-        // SIGINT handler (Ctrl+C)
-        let ctrl_c = async {
-            signal::ctrl_c().await.expect("Failed to install CTRL+C handler");
-            tracing::info!("Received SIGINT (Ctrl+C), shutting down");
-        };
 
-        // SIGTERM handler (docker stop, kill -15, etc.)
-        #[cfg(unix)]
-        let terminate = async {
-            signal::unix::signal(signal::unix::SignalKind::terminate())
-                .expect("Failed to install SIGTERM handler")
-                .recv()
-                .await;
-            tracing::info!("Received SIGTERM, shutting down");
-        };
+    let tls_paths = match (env::var("TLS_CERT_PATH"), env::var("TLS_KEY_PATH")) {
+        (Ok(cert), Ok(key)) => Some((cert, key)),
+        _ => None,
+    };
 
-        #[cfg(not(unix))]
-        let terminate = std::future::pending::<()>();
+    if let Some((cert_path, key_path)) = tls_paths {
+        let tls_config = RustlsConfig::from_pem_file(&cert_path, &key_path).await?;
+
+        let handle = Handle::new();
+        let shutdown_handle = handle.clone();
+        tokio::spawn(async move {
+            shutdown_signal().await;
+            shutdown_handle.graceful_shutdown(Some(Duration::from_secs(10)));
+        });
+
+        tracing::info!("Listening on {} (TLS)", addr);
+        axum_server::bind_rustls(addr, tls_config)
+            .handle(handle)
+            .serve(app.into_make_service())
+            .await?;
+    } else {
+        tracing::info!("Listening on {}", addr);
+        let listener = tokio::net::TcpListener::bind(&addr).await?;
+
+        axum::serve(listener, app)
+            .with_graceful_shutdown(shutdown_signal())
+            .await?;
+    }
 
-        // Wait for either signal
-        tokio::select! {
-            _ = ctrl_c => {},
-            _ = terminate => {},
-        }
+    Ok(())
+}
+
+// Graceful shutdown: listen for SIGINT or SIGTERM
+async fn shutdown_signal() {
+    use tokio::signal;
+
+    // SIGINT handler (Ctrl+C)
+    let ctrl_c = async {
+        signal::ctrl_c().await.expect("Failed to install CTRL+C handler");
+        tracing::info!("Received SIGINT (Ctrl+C), shutting down");
     };
 
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal)
-        .await?;
-    Ok(())
+    // SIGTERM handler (docker stop, kill -15, etc.)
+    #[cfg(unix)]
+    let terminate = async {
+        signal::unix::signal(signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+        tracing::info!("Received SIGTERM, shutting down");
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    // Wait for either signal
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+// Periodically remove expired links from the cache and the persistent store
+async fn sweep_expired_urls(state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(60));
+    loop {
+        interval.tick().await;
+
+        let expired: Vec<String> = state
+            .urls
+            .iter()
+            .filter(|entry| entry.value().is_expired())
+            .map(|entry| entry.key().clone())
+            .collect();
+
+        for short_code in expired {
+            state.urls.remove(&short_code);
+            if let Err(e) = state.store.remove(&short_code) {
+                tracing::error!("Failed to remove expired {} from store: {:?}", short_code, e);
+            }
+        }
+    }
 }
 
 // Handlers
@@ -168,32 +340,91 @@ async fn create_short_url(
 ) -> Result<Json<UrlResponse>> {
     let start = Instant::now();
     
-    // Basic URL validation 
+    // Basic URL validation
     if !payload.url.starts_with("http://") && !payload.url.starts_with("https://") {
         return Err(AppError::InvalidUrl);
     }
-    
-    // Generate a short code (only clone once for the URL struct)
-    let short_code = nanoid!(6);
-    
+
+    // Validate the custom code's shape before doing any network work
+    if let Some(custom_code) = &payload.custom_code {
+        if !is_valid_custom_code(custom_code) {
+            return Err(AppError::InvalidUrl);
+        }
+    }
+
+    // A zero or negative TTL would create a link that's already expired
+    if payload.ttl_seconds.is_some_and(|ttl| ttl <= 0) {
+        return Err(AppError::InvalidUrl);
+    }
+
+    // Resolve redirects up front so the short link points directly at the
+    // final destination, when verification is enabled
+    let original_url = if state.verify_urls {
+        resolve::resolve(&state.http_client, &payload.url)
+            .await
+            .map_err(|_| AppError::Unreachable)?
+    } else {
+        payload.url
+    };
+
     // Get current time
     let now = Utc::now();
-    
+
     // Get base URL from environment or use default
     let base_url = env::var("BASE_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
-    
+
+    // Optional TTL: an entry whose expires_at has passed is treated as gone
+    let expires_at = payload
+        .ttl_seconds
+        .map(|ttl| now + chrono::Duration::seconds(ttl));
+
     // Create URL object (avoid cloning where possible)
-    let url = Url {
+    let mut url = Url {
         id: nanoid!(10),
-        original_url: payload.url,
-        short_code: short_code.clone(),
+        original_url,
+        short_code: String::new(),
         created_at: now,
         access_count: 0,
+        expires_at,
     };
-    
-    // Save to in-memory store
-    state.urls.insert(short_code.clone(), url.clone());
-    
+
+    // Reserve the short code in the hot cache via the entry API so a
+    // concurrent request can't pass the existence check and overwrite it
+    let short_code = match payload.custom_code {
+        Some(custom_code) => {
+            url.short_code = custom_code.clone();
+            match state.urls.entry(custom_code.clone()) {
+                dashmap::mapref::entry::Entry::Occupied(_) => return Err(AppError::CodeTaken),
+                dashmap::mapref::entry::Entry::Vacant(entry) => {
+                    entry.insert(url.clone());
+                }
+            }
+            custom_code
+        }
+        None => loop {
+            let candidate = nanoid!(6);
+            url.short_code = candidate.clone();
+            match state.urls.entry(candidate.clone()) {
+                dashmap::mapref::entry::Entry::Vacant(entry) => {
+                    entry.insert(url.clone());
+                    break candidate;
+                }
+                dashmap::mapref::entry::Entry::Occupied(_) => continue,
+            }
+        },
+    };
+
+    // Write-through to the persistent store now that the code is reserved
+    if let Err(e) = state.store.insert(&url) {
+        tracing::error!("Failed to persist {}: {:?}", short_code, e);
+    }
+
+    // Notify SSE subscribers; ignore the error if nobody is listening
+    let _ = state.analytics_tx.send(AnalyticsEvent::UrlCreated {
+        short_code: short_code.clone(),
+        original_url: url.original_url.clone(),
+    });
+
     // Log the time taken
     let elapsed = start.elapsed();
     tracing::debug!("[create_short_url] Time taken: {:?}", elapsed);
@@ -207,6 +438,7 @@ async fn create_short_url(
         short_url,
         created_at: url.created_at,
         access_count: url.access_count,
+        expires_at: url.expires_at,
     }))
 }
 
@@ -218,11 +450,39 @@ async fn redirect_to_original(
     // Get the URL entry if it exists
     let mut entry = state.urls.get_mut(&short_code).ok_or(AppError::NotFound)?;
 
+    // An expired link is gone, even if it hasn't been swept yet
+    if entry.is_expired() {
+        return Err(AppError::Gone);
+    }
+
     // Increment access count
     entry.access_count += 1;
+    let original_url = entry.original_url.clone();
+    let access_count = entry.access_count;
+    // Snapshot the post-increment state while still holding the DashMap
+    // entry lock, so the persisted count can't race with another redirect
+    // re-deriving it from a separately read (and possibly stale) store value
+    let snapshot = entry.clone();
+    drop(entry);
+
+    // Notify SSE subscribers; ignore the error if nobody is listening
+    let _ = state.analytics_tx.send(AnalyticsEvent::Click {
+        short_code: short_code.clone(),
+        access_count,
+    });
+
+    // Hand off to the single persistence writer task instead of spawning an
+    // independent write per click, so same-code writes land on disk in the
+    // same order they happened in the DashMap
+    if state.persist_tx.send(snapshot).is_err() {
+        tracing::error!(
+            "Persistence writer task is gone; access count for {} won't be saved",
+            short_code
+        );
+    }
 
     // Redirect to the original URL (clone only what we need)
-    Ok(Redirect::permanent(&entry.original_url))
+    Ok(Redirect::permanent(&original_url))
 }
 
 // Get all URLs
@@ -241,6 +501,7 @@ async fn get_all_urls(State(state): State<Arc<AppState>>) -> Result<Json<Vec<Url
                 short_url: format!("{}/{}", base_url, url.short_code),
                 created_at: url.created_at,
                 access_count: url.access_count,
+                expires_at: url.expires_at,
             }
         })
         .collect();
@@ -272,6 +533,7 @@ async fn get_analytics(State(state): State<Arc<AppState>>) -> Result<Json<Analyt
                 short_url: format!("{}/{}", base_url, url.short_code),
                 created_at: url.created_at,
                 access_count: url.access_count,
+                expires_at: url.expires_at,
             }
         })
         .collect();
@@ -285,3 +547,21 @@ async fn get_analytics(State(state): State<Arc<AppState>>) -> Result<Json<Analyt
         urls: url_responses,
     }))
 }
+
+// Live analytics feed: url_created / click events, plus a keep-alive so
+// proxies don't drop idle connections.
+async fn analytics_stream(
+    State(state): State<Arc<AppState>>,
+) -> Sse<impl Stream<Item = std::result::Result<Event, Infallible>>> {
+    let rx = state.analytics_tx.subscribe();
+    let stream = BroadcastStream::new(rx).filter_map(|event| match event {
+        Ok(event) => Some(Ok(Event::default().json_data(event).unwrap_or_default())),
+        Err(_) => None,
+    });
+
+    Sse::new(stream).keep_alive(
+        KeepAlive::new()
+            .interval(Duration::from_secs(15))
+            .text("keep-alive"),
+    )
+}
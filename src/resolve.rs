@@ -0,0 +1,129 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::Duration;
+
+use reqwest::{Client, StatusCode, Url, redirect::Policy};
+
+const MAX_HOPS: u8 = 5;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Build the client used to verify target URLs. Redirects are disabled so
+/// each hop's `Location` header can be resolved and followed by hand.
+pub fn client() -> Client {
+    Client::builder()
+        .redirect(Policy::none())
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .expect("failed to build URL-verification client")
+}
+
+/// Follow redirects from `start_url` up to `MAX_HOPS` times and return the
+/// final destination, or an error if the target is unreachable or redirects
+/// too many times.
+///
+/// Every hop's host is resolved and checked against private/loopback/
+/// link-local/multicast ranges before it's contacted, since a redirect can
+/// point somewhere entirely different from the original host (SSRF).
+pub async fn resolve(client: &Client, start_url: &str) -> anyhow::Result<String> {
+    let mut current = Url::parse(start_url)?;
+
+    for _ in 0..MAX_HOPS {
+        assert_host_is_public(&current).await?;
+
+        let (status, location) = probe(client, &current).await?;
+
+        if !status.is_redirection() {
+            return Ok(current.to_string());
+        }
+
+        let location = location.ok_or_else(|| {
+            anyhow::anyhow!("redirect from {} had no Location header", current)
+        })?;
+
+        // Url::join already implements RFC 3986 §4.2 relative resolution:
+        // absolute URLs, scheme-relative ("//host/path"), absolute paths
+        // ("/path") and relative paths all resolve against `current`.
+        current = current.join(&location)?;
+    }
+
+    anyhow::bail!("too many redirects resolving {}", start_url)
+}
+
+async fn probe(client: &Client, url: &Url) -> anyhow::Result<(StatusCode, Option<String>)> {
+    let mut response = client.head(url.clone()).send().await?;
+
+    // Some servers reject HEAD; fall back to GET for those.
+    if response.status() == StatusCode::METHOD_NOT_ALLOWED {
+        response = client.get(url.clone()).send().await?;
+    }
+
+    let location = response
+        .headers()
+        .get(reqwest::header::LOCATION)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string());
+
+    Ok((response.status(), location))
+}
+
+/// Resolve `url`'s host and reject it if any resolved address is not
+/// globally routable, so `VERIFY_URLS` can't be used to probe the
+/// deployment's internal network (loopback, RFC 1918 ranges, link-local,
+/// cloud metadata endpoints, etc).
+async fn assert_host_is_public(url: &Url) -> anyhow::Result<()> {
+    let host = url
+        .host_str()
+        .ok_or_else(|| anyhow::anyhow!("URL has no host: {}", url))?;
+    let port = url.port_or_known_default().unwrap_or(80);
+
+    let mut addrs = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to resolve {}: {}", host, e))?
+        .peekable();
+
+    if addrs.peek().is_none() {
+        anyhow::bail!("host {} did not resolve to any address", host);
+    }
+
+    for addr in addrs {
+        if !is_globally_routable(addr.ip()) {
+            anyhow::bail!(
+                "refusing to contact non-public address {} for host {}",
+                addr.ip(),
+                host
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn is_globally_routable(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_v4_globally_routable(ip),
+        IpAddr::V6(ip) => is_v6_globally_routable(ip),
+    }
+}
+
+fn is_v4_globally_routable(ip: Ipv4Addr) -> bool {
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_multicast()
+        || ip.is_broadcast()
+        || ip.is_unspecified()
+        || ip.is_documentation())
+}
+
+fn is_v6_globally_routable(ip: Ipv6Addr) -> bool {
+    let segments = ip.segments();
+    // Unique local (fc00::/7) and unicast link-local (fe80::/10) aren't
+    // exposed as stable `Ipv6Addr` methods yet, so check the prefix by hand.
+    let is_unique_local = (segments[0] & 0xfe00) == 0xfc00;
+    let is_unicast_link_local = (segments[0] & 0xffc0) == 0xfe80;
+
+    !(ip.is_loopback()
+        || ip.is_multicast()
+        || ip.is_unspecified()
+        || is_unique_local
+        || is_unicast_link_local)
+}
@@ -0,0 +1,54 @@
+use anyhow::Result;
+
+use crate::Url;
+
+/// Durable key-value storage backing the in-memory `DashMap` cache.
+///
+/// Implementations are expected to be cheap to clone (wrapped in an `Arc`)
+/// and safe to call from multiple tasks concurrently.
+pub trait Store: Send + Sync {
+    fn get(&self, short_code: &str) -> Result<Option<Url>>;
+    fn insert(&self, url: &Url) -> Result<()>;
+    fn remove(&self, short_code: &str) -> Result<()>;
+    fn iter(&self) -> Result<Vec<Url>>;
+}
+
+/// `sled`-backed implementation of [`Store`], one embedded DB file per deployment.
+pub struct SledStore {
+    db: sled::Db,
+}
+
+impl SledStore {
+    pub fn open(path: &str) -> Result<Self> {
+        let db = sled::open(path)?;
+        Ok(Self { db })
+    }
+}
+
+impl Store for SledStore {
+    fn get(&self, short_code: &str) -> Result<Option<Url>> {
+        match self.db.get(short_code)? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn insert(&self, url: &Url) -> Result<()> {
+        let bytes = serde_json::to_vec(url)?;
+        self.db.insert(&url.short_code, bytes)?;
+        Ok(())
+    }
+
+    fn remove(&self, short_code: &str) -> Result<()> {
+        self.db.remove(short_code)?;
+        Ok(())
+    }
+
+    fn iter(&self) -> Result<Vec<Url>> {
+        self.db
+            .iter()
+            .values()
+            .map(|v| Ok(serde_json::from_slice(&v?)?))
+            .collect()
+    }
+}
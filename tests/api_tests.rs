@@ -1,14 +1,42 @@
 use reqwest;
 use serde_json::Value;
 use std::process::{Child, Command};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::thread::sleep;
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+static TEST_DB_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+// A fresh sled DB path per test run, so tests don't see state persisted by
+// a previous run (or by another test) via the default DATABASE_PATH.
+fn temp_db_path() -> std::path::PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let count = TEST_DB_COUNTER.fetch_add(1, Ordering::Relaxed);
+    std::env::temp_dir().join(format!(
+        "url-short-rust-test-{}-{}-{}",
+        std::process::id(),
+        nanos,
+        count
+    ))
+}
 
 // Helper function to start the server
 fn start_server() -> Child {
-    println!("Starting server...");
+    start_server_with_env(&[])
+}
+
+// Helper function to start the server with extra environment variables set,
+// e.g. to exercise JWT auth which is otherwise disabled by default. Always
+// runs against an isolated, throwaway DATABASE_PATH.
+fn start_server_with_env(vars: &[(&str, &str)]) -> Child {
+    println!("Starting server with env overrides...");
     let server = Command::new("cargo")
         .args(["run", "--release"])
+        .env("DATABASE_PATH", temp_db_path())
+        .envs(vars.iter().copied())
         .spawn()
         .expect("Failed to start the server");
 
@@ -148,3 +176,163 @@ async fn test_not_found() {
     // Stop the server
     server.kill().expect("Failed to kill the server");
 }
+
+// Test that management endpoints require a token once JWT_SECRET is set
+#[tokio::test]
+async fn test_auth_required_without_token() {
+    let mut server = start_server_with_env(&[("JWT_SECRET", "test-secret")]);
+
+    let client = reqwest::Client::new();
+    let response = client
+        .get("http://localhost:3000/api/analytics")
+        .send()
+        .await
+        .expect("Failed to execute request");
+
+    assert_eq!(response.status().as_u16(), 401); // Unauthorized
+
+    // A valid login should still issue a usable token
+    let login_response = client
+        .post("http://localhost:3000/api/login")
+        .json(&serde_json::json!({
+            "username": "admin",
+            "password": "admin"
+        }))
+        .send()
+        .await
+        .expect("Failed to execute login request");
+    assert!(login_response.status().is_success());
+
+    let login_body = login_response
+        .json::<Value>()
+        .await
+        .expect("Failed to parse login response");
+    let token = login_body["token"].as_str().unwrap();
+
+    let authed_response = client
+        .get("http://localhost:3000/api/analytics")
+        .bearer_auth(token)
+        .send()
+        .await
+        .expect("Failed to execute authenticated request");
+    assert!(authed_response.status().is_success());
+
+    // Stop the server
+    server.kill().expect("Failed to kill the server");
+}
+
+// Test that a link past its TTL is treated as gone
+#[tokio::test]
+async fn test_ttl_expiry() {
+    let mut server = start_server();
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post("http://localhost:3000/api/shorten")
+        .json(&serde_json::json!({
+            "url": "https://www.rust-lang.org",
+            "ttl_seconds": 1
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request");
+    assert!(response.status().is_success());
+
+    let body = response
+        .json::<Value>()
+        .await
+        .expect("Failed to parse response");
+    assert!(body.get("expires_at").is_some());
+    let short_code = body["short_code"].as_str().unwrap();
+
+    // Wait for the TTL to elapse
+    sleep(Duration::from_secs(2));
+
+    let redirect_response = client
+        .get(format!("http://localhost:3000/{}", short_code))
+        .send()
+        .await
+        .expect("Failed to execute redirect request");
+
+    assert_eq!(redirect_response.status().as_u16(), 410); // Gone
+
+    // Stop the server
+    server.kill().expect("Failed to kill the server");
+}
+
+// Test requesting a custom alias that's already taken
+#[tokio::test]
+async fn test_custom_alias_collision() {
+    let mut server = start_server();
+
+    let client = reqwest::Client::new();
+    let first = client
+        .post("http://localhost:3000/api/shorten")
+        .json(&serde_json::json!({
+            "url": "https://www.rust-lang.org",
+            "custom_code": "my-alias"
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request");
+    assert!(first.status().is_success());
+
+    let second = client
+        .post("http://localhost:3000/api/shorten")
+        .json(&serde_json::json!({
+            "url": "https://docs.rs",
+            "custom_code": "my-alias"
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request");
+
+    assert_eq!(second.status().as_u16(), 409); // Conflict
+
+    // Stop the server
+    server.kill().expect("Failed to kill the server");
+}
+
+// Test that a custom alias can't shadow a reserved route prefix
+#[tokio::test]
+async fn test_custom_alias_reserved_path() {
+    let mut server = start_server();
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post("http://localhost:3000/api/shorten")
+        .json(&serde_json::json!({
+            "url": "https://www.rust-lang.org",
+            "custom_code": "api"
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request");
+
+    assert_eq!(response.status().as_u16(), 400); // Bad Request
+
+    // Stop the server
+    server.kill().expect("Failed to kill the server");
+}
+
+// Test that a custom alias with disallowed characters is rejected
+#[tokio::test]
+async fn test_custom_alias_invalid_chars() {
+    let mut server = start_server();
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post("http://localhost:3000/api/shorten")
+        .json(&serde_json::json!({
+            "url": "https://www.rust-lang.org",
+            "custom_code": "not a valid code!"
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request");
+
+    assert_eq!(response.status().as_u16(), 400); // Bad Request
+
+    // Stop the server
+    server.kill().expect("Failed to kill the server");
+}